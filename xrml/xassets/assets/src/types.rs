@@ -5,12 +5,13 @@ use parity_codec::{Decode, Encode};
 use serde_derive::{Deserialize, Serialize};
 
 // Substrate
-use rstd::{prelude::*, result, slice::Iter};
+use core::{convert::TryFrom, iter, ops};
+use rstd::{marker::PhantomData, prelude::*, result, slice::Iter};
 use support::dispatch::Result;
-use support::traits::{Imbalance, SignedImbalance};
+use support::traits::{Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, SignedImbalance, WithdrawReasons};
 use support::StorageMap;
 
-use primitives::traits::{Saturating, Zero};
+use primitives::traits::{CheckedAdd, Saturating, Zero};
 // ChainX
 pub use xr_primitives::{Desc, Memo, Token};
 
@@ -26,14 +27,158 @@ pub type TokenString = &'static [u8];
 pub type DescString = TokenString;
 pub type Precision = u16;
 
-pub type SignedImbalanceT<T> = SignedImbalance<<T as Trait>::Balance, PositiveImbalance<T>>;
+/// Smallest-unit scaling factor matching the 8 decimal places most assets in this module use.
+pub const COIN: i128 = 100_000_000;
+/// Fixed token-count cap shared by every asset registered through this module; an individual
+/// asset's actual monetary cap in minimal units is this value scaled by its own `precision`
+/// (see `Asset::new`), not by the 8-decimal `COIN` above.
+const TOTAL_SUPPLY_CAP: i128 = 21_000_000;
+/// Default valid range for a bare `Amount`, i.e. one not tied to a specific asset's precision.
+pub const MAX_MONEY: i128 = TOTAL_SUPPLY_CAP * COIN;
 
-#[derive(PartialEq, Eq, Ord, PartialOrd, Clone, Copy, Encode, Decode)]
+/// A checked, range-bounded monetary amount, modelled on Zcash's `Amount`.
+///
+/// Unlike the raw `T::Balance` arithmetic used elsewhere in this module (the imbalance
+/// `merge`/`subsume` impls below both `saturating_add`), `Amount` can only ever be
+/// constructed with a value in `-max_money..=max_money`, and every arithmetic operation fails
+/// rather than wrapping or clamping, so a broken invariant surfaces immediately instead of
+/// silently skewing `TotalAssetBalance`. `max_money` travels with the value rather than being
+/// a single global constant, since each asset's actual cap depends on its own `precision` (see
+/// `Asset::max_money`); `MAX_MONEY` is only the default bound for an `Amount` not tied to a
+/// specific asset.
+#[derive(Clone, Copy, Default, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
-pub enum Chain {
-    ChainX,
-    Bitcoin,
-    Ethereum,
+pub struct Amount {
+    value: i128,
+    max_money: i128,
+}
+
+impl Amount {
+    pub fn zero() -> Self {
+        Amount { value: 0, max_money: MAX_MONEY }
+    }
+
+    /// Builds an `Amount` bounded by `MAX_MONEY`, rejecting anything outside
+    /// `-MAX_MONEY..=MAX_MONEY`.
+    pub fn from_i128(value: i128) -> result::Result<Self, &'static str> {
+        Self::from_i128_bounded(value, MAX_MONEY)
+    }
+
+    /// Builds an `Amount` bounded by an asset-specific `max_money` (e.g. `Asset::max_money`),
+    /// rejecting anything outside `-max_money..=max_money`.
+    pub fn from_i128_bounded(value: i128, max_money: i128) -> result::Result<Self, &'static str> {
+        if value < -max_money || value > max_money {
+            return Err("amount is outside the valid money range");
+        }
+        Ok(Amount { value, max_money })
+    }
+
+    /// Builds an `Amount` from an `i64` literal, for constants already known to be in range.
+    pub fn const_from_i64(value: i64) -> result::Result<Self, &'static str> {
+        Self::from_i128(value as i128)
+    }
+
+    pub fn value(self) -> i128 {
+        self.value
+    }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+impl Eq for Amount {}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl ops::Add for Amount {
+    type Output = Option<Amount>;
+    fn add(self, rhs: Amount) -> Option<Amount> {
+        let max_money = self.max_money.max(rhs.max_money);
+        self.value
+            .checked_add(rhs.value)
+            .and_then(|v| Amount::from_i128_bounded(v, max_money).ok())
+    }
+}
+
+impl ops::Sub for Amount {
+    type Output = Option<Amount>;
+    fn sub(self, rhs: Amount) -> Option<Amount> {
+        let max_money = self.max_money.max(rhs.max_money);
+        self.value
+            .checked_sub(rhs.value)
+            .and_then(|v| Amount::from_i128_bounded(v, max_money).ok())
+    }
+}
+
+impl iter::Sum<Amount> for Option<Amount> {
+    fn sum<I: Iterator<Item = Amount>>(mut iter: I) -> Self {
+        let first = iter.next()?;
+        iter.fold(Some(first), |acc, x| acc.and_then(|a| a + x))
+    }
+}
+
+pub type SignedImbalanceT<T> = SignedImbalance<<T as Trait>::Balance, PositiveImbalance<T>>;
+pub type NegativeImbalanceT<T> = NegativeImbalance<T>;
+pub type PositiveImbalanceT<T> = PositiveImbalance<T>;
+
+/// ID of a chain registered in the chain registry. The three built-ins below keep their
+/// historical reserved IDs; anything registered later (e.g. by the gateway subsystem
+/// onboarding a new settlement chain) gets an ID at or above `RESERVED_CHAIN_COUNT`.
+pub type ChainId = u32;
+
+pub const CHAINX_CHAIN_ID: ChainId = 0;
+pub const BITCOIN_CHAIN_ID: ChainId = 1;
+pub const ETHEREUM_CHAIN_ID: ChainId = 2;
+/// Number of reserved, compile-time-known chain IDs; see `Chain`.
+pub const RESERVED_CHAIN_COUNT: ChainId = 3;
+
+/// Declares a unit-only enum together with an `iterator()` derived straight from its variant
+/// list, the way `enum-iterator`'s derive macro would, since this crate's no_std/parity_codec
+/// dependency graph doesn't carry a proc-macro crate to derive it from. The variant list is
+/// written exactly once — inside the macro invocation — instead of being duplicated into a
+/// separately hand-maintained `static` array that can drift out of sync as variants are added.
+macro_rules! derive_variant_iter {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident { $($variant:ident),+ $(,)? }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            /// Iterates over every variant of `$name`, in declaration order.
+            pub fn iterator() -> Iter<'static, $name> {
+                const VARIANTS: &[$name] = &[$($name::$variant),+];
+                VARIANTS.iter()
+            }
+        }
+    };
+}
+
+derive_variant_iter! {
+    /// The chains this module shipped with from the start. New chains no longer require a new
+    /// variant here — see `ChainInfo` and the `ChainRegistry` storage item this type's ID is
+    /// looked up against in `Asset::is_valid`.
+    #[derive(PartialEq, Eq, Ord, PartialOrd, Clone, Copy, Encode, Decode)]
+    #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+    pub enum Chain {
+        ChainX,
+        Bitcoin,
+        Ethereum,
+    }
 }
 
 impl Default for Chain {
@@ -43,45 +188,88 @@ impl Default for Chain {
 }
 
 impl Chain {
-    pub fn iterator() -> Iter<'static, Chain> {
-        static CHAINS: [Chain; 3] = [Chain::ChainX, Chain::Bitcoin, Chain::Ethereum];
-        CHAINS.iter()
+    /// The reserved `ChainId` this built-in variant is registered under.
+    pub fn id(self) -> ChainId {
+        match self {
+            Chain::ChainX => CHAINX_CHAIN_ID,
+            Chain::Bitcoin => BITCOIN_CHAIN_ID,
+            Chain::Ethereum => ETHEREUM_CHAIN_ID,
+        }
     }
 }
 
+/// Metadata for a chain registered in the chain registry, covering both the three reserved
+/// built-ins (seeded at genesis) and any chain onboarded later via governance.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub struct ChainInfo {
+    pub id: ChainId,
+    pub name: Token,
+}
+
+/// Yields every chain currently registered — the three reserved built-ins plus any onboarded
+/// later — for callers that used to rely on `Chain::iterator()` seeing every valid chain
+/// rather than just the compile-time built-ins.
+pub fn registered_chains<T: Trait>() -> Vec<ChainInfo> {
+    crate::ChainRegistry::<T>::enumerate()
+        .map(|(_, info)| info)
+        .collect()
+}
+
+/// NOTE ON STORAGE MIGRATION: this struct's SCALE encoding has changed twice since its field
+/// list was `{token, token_name, chain: Chain, precision, desc}` — `chain: Chain` became
+/// `chain_id: ChainId` (a different encoding, not just a rename), and `max_money`/`accept_fee`
+/// were appended. A chain with assets already registered under the old encoding will fail to
+/// decode existing `AssetInfo` entries after upgrading to this version; deploying this change
+/// requires either a storage migration that re-encodes every existing entry, or bundling this
+/// pallet's release with a chain that has no prior `AssetInfo` state to decode.
 #[derive(PartialEq, Eq, Clone, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 #[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct Asset {
     token: Token,
     token_name: Token,
-    chain: Chain,
+    chain_id: ChainId,
     precision: Precision,
     desc: Desc,
+    max_money: i128,
+    accept_fee: bool,
 }
 
 impl Asset {
-    pub fn new(
+    pub fn new<T: Trait>(
         token: Token,
         token_name: Token,
-        chain: Chain,
+        chain_id: ChainId,
         precision: Precision,
         desc: Desc,
     ) -> result::Result<Self, &'static str> {
+        let scale = 10i128
+            .checked_pow(precision as u32)
+            .ok_or("asset precision is too large to derive a maximum supply")?;
+        let max_money = TOTAL_SUPPLY_CAP
+            .checked_mul(scale)
+            .ok_or("asset precision is too large to derive a maximum supply")?;
         let a = Asset {
             token,
             token_name,
-            chain,
+            chain_id,
             precision,
             desc,
+            max_money,
+            accept_fee: false,
         };
-        a.is_valid()?;
+        a.is_valid::<T>()?;
         Ok(a)
     }
-    pub fn is_valid(&self) -> Result {
+    pub fn is_valid<T: Trait>(&self) -> Result {
         is_valid_token(&self.token)?;
         is_valid_token_name(&self.token_name)?;
-        is_valid_desc(&self.desc)
+        is_valid_desc(&self.desc)?;
+        if !crate::ChainRegistry::<T>::exists(&self.chain_id) {
+            return Err("chain id is not registered in the chain registry");
+        }
+        Ok(())
     }
 
     pub fn token(&self) -> Token {
@@ -90,8 +278,8 @@ impl Asset {
     pub fn token_name(&self) -> Token {
         self.token_name.clone()
     }
-    pub fn chain(&self) -> Chain {
-        self.chain
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
     }
     pub fn desc(&self) -> Desc {
         self.desc.clone()
@@ -102,6 +290,251 @@ impl Asset {
     pub fn precision(&self) -> Precision {
         self.precision
     }
+    /// Whether this token may be used to pay transaction fees via `TokenCurrency`.
+    pub fn accept_fee(&self) -> bool {
+        self.accept_fee
+    }
+    pub fn set_accept_fee(&mut self, accept_fee: bool) {
+        self.accept_fee = accept_fee
+    }
+    /// This token's maximum total issuance in minimal units, i.e. `21,000,000 * 10^precision`.
+    pub fn max_money(&self) -> i128 {
+        self.max_money
+    }
+
+    /// Checks that minting `by` additional minimal units would not push this token's total
+    /// issuance past `max_money`, returning the new total on success. Deposit/issue paths
+    /// should call this before crediting any balance, instead of relying on `TotalAssetBalance`
+    /// saturating silently at the `T::Balance` boundary.
+    pub fn checked_total_after_mint<T: Trait>(
+        &self,
+        current_total: T::Balance,
+        by: T::Balance,
+    ) -> result::Result<T::Balance, &'static str>
+    where
+        T::Balance: primitives::traits::As<u128>,
+    {
+        let new_total = current_total.checked_add(&by).ok_or("total issuance overflow")?;
+        // Convert through `As<u128>`, not `As<u64>`: `max_money` is derived in `i128` and can
+        // legitimately exceed `u64::MAX` (e.g. a bridged asset with 18-decimal precision), so
+        // routing `new_total` through `u64` first would truncate it and let the cap check pass
+        // on garbage. `T::Balance` itself is never wider than `u128` in this module, so this
+        // conversion is exact.
+        let new_total_i128 =
+            i128::try_from(new_total.as_()).map_err(|_| "total issuance overflow")?;
+        // Route the bound check through `Amount` rather than comparing `self.max_money` raw,
+        // so the same overflow-checked range logic used everywhere else in this module backs
+        // the one place it actually matters: the cap on an asset's total issuance.
+        Amount::from_i128_bounded(new_total_i128, self.max_money)
+            .map_err(|_| "mint would exceed this asset's maximum issuance")?;
+        Ok(new_total)
+    }
+
+    /// Parses a human-readable decimal amount such as `"1.2345"` into this token's minimal
+    /// units, using `self.precision` for the scale and `self.max_money` for the overflow
+    /// bound.
+    pub fn parse_decimal(&self, input: &str) -> result::Result<i128, DecimalStrErr> {
+        parse_decimal_str(input, self.precision, self.max_money)
+    }
+
+    /// Formats a minimal-unit amount of this token as a human-readable decimal string, using
+    /// `self.precision` for the scale.
+    pub fn format_decimal(&self, value: i128) -> result::Result<FixedDecimalStr, &'static str> {
+        format_decimal_str(value, self.precision)
+    }
+}
+
+/// Upper bound on the formatted decimal string length `FixedDecimalStr` can hold. A formatted
+/// amount is a sign, the at most 39 digits of an `i128` magnitude split between the integer
+/// and fractional parts, and a decimal point: at most 41 bytes, since any precision above 38
+/// is rejected before formatting (`10^precision` must fit an `i128`).
+const MAX_DECIMAL_STR_LEN: usize = 48;
+
+/// Fixed-capacity byte buffer used as the write target for `format_decimal_str`, so the
+/// no_std path never allocates — the same reason `rust-bitcoin`'s `Amount` formatting writes
+/// into a stack buffer instead of building a `String`.
+#[derive(Clone, Copy)]
+pub struct FixedDecimalStr {
+    buf: [u8; MAX_DECIMAL_STR_LEN],
+    len: usize,
+}
+
+impl FixedDecimalStr {
+    fn empty() -> Self {
+        FixedDecimalStr {
+            buf: [0u8; MAX_DECIMAL_STR_LEN],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, b: u8) -> result::Result<(), &'static str> {
+        if self.len >= MAX_DECIMAL_STR_LEN {
+            return Err("formatted amount exceeds the fixed decimal buffer");
+        }
+        self.buf[self.len] = b;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len])
+            .expect("only ASCII digits, '-' and '.' are ever written")
+    }
+}
+
+impl core::ops::Deref for FixedDecimalStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Why a decimal amount string failed to parse.
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum DecimalStrErr {
+    /// More fractional digits than the token's `precision` allows.
+    TooManyFractionalDigits,
+    /// More than one `.` in the input.
+    MultipleDecimalPoints,
+    /// A byte other than an ASCII digit, `.`, or a leading `-`.
+    InvalidChar,
+    /// The scaled value overflows `i128` or exceeds the token's `max_money`.
+    Overflow,
+}
+
+impl DecimalStrErr {
+    pub fn info(self) -> &'static str {
+        match self {
+            DecimalStrErr::TooManyFractionalDigits => {
+                "more fractional digits than this asset's precision allows"
+            }
+            DecimalStrErr::MultipleDecimalPoints => {
+                "a decimal amount can only contain a single '.'"
+            }
+            DecimalStrErr::InvalidChar => {
+                "a decimal amount can only contain ASCII digits, a single '.' and a leading '-'"
+            }
+            DecimalStrErr::Overflow => "decimal amount overflows this asset's maximum issuance",
+        }
+    }
+}
+
+/// Parses a human-readable decimal amount (e.g. `"1.2345"` or `"-1.2345"`) into the integer
+/// minimal-unit representation for a token with the given `precision`, rejecting more
+/// fractional digits than `precision` allows, more than one `.`, and any byte that isn't an
+/// ASCII digit, a leading `-`, or `.`. `max_money` bounds the result the same way
+/// `Amount::from_i128_bounded` bounds a signed amount, and this function round-trips with
+/// `format_decimal_str`, which emits the same leading `-` for negative values.
+pub fn parse_decimal_str(
+    input: &str,
+    precision: Precision,
+    max_money: i128,
+) -> result::Result<i128, DecimalStrErr> {
+    let (negative, input) = match input.as_bytes().first() {
+        Some(b'-') => (true, &input[1..]),
+        _ => (false, input),
+    };
+    let bytes = input.as_bytes();
+    let mut dot = None;
+    for (i, b) in bytes.iter().enumerate() {
+        match b {
+            b'0'..=b'9' => {}
+            b'.' if dot.is_none() => dot = Some(i),
+            b'.' => return Err(DecimalStrErr::MultipleDecimalPoints),
+            _ => return Err(DecimalStrErr::InvalidChar),
+        }
+    }
+    let (int_part, frac_part) = match dot {
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => (input, ""),
+    };
+    if frac_part.len() > precision as usize {
+        return Err(DecimalStrErr::TooManyFractionalDigits);
+    }
+
+    let scale = 10i128
+        .checked_pow(precision as u32)
+        .ok_or(DecimalStrErr::Overflow)?;
+    let int_value: i128 = if int_part.is_empty() {
+        0
+    } else {
+        int_part.parse().map_err(|_| DecimalStrErr::Overflow)?
+    };
+    let frac_value: i128 = if frac_part.is_empty() {
+        0
+    } else {
+        frac_part.parse().map_err(|_| DecimalStrErr::Overflow)?
+    };
+    let frac_scale = 10i128
+        .checked_pow((precision as usize - frac_part.len()) as u32)
+        .ok_or(DecimalStrErr::Overflow)?;
+
+    let value = int_value
+        .checked_mul(scale)
+        .and_then(|v| frac_value.checked_mul(frac_scale).map(|f| (v, f)))
+        .and_then(|(v, f)| v.checked_add(f))
+        .ok_or(DecimalStrErr::Overflow)?;
+    let value = if negative { value.checked_neg().ok_or(DecimalStrErr::Overflow)? } else { value };
+    if value > max_money || value < -max_money {
+        return Err(DecimalStrErr::Overflow);
+    }
+    Ok(value)
+}
+
+/// Formats an integer minimal-unit amount as a human-readable decimal string for a token with
+/// the given `precision`, trimming trailing fractional zeros (and the `.` itself when nothing
+/// remains), writing into a fixed-capacity buffer so the no_std path never allocates.
+pub fn format_decimal_str(
+    value: i128,
+    precision: Precision,
+) -> result::Result<FixedDecimalStr, &'static str> {
+    let mut out = FixedDecimalStr::empty();
+    if value < 0 {
+        out.push(b'-')?;
+    }
+    let magnitude = value.checked_abs().ok_or("amount magnitude overflow")?;
+    let scale = 10i128
+        .checked_pow(precision as u32)
+        .ok_or("asset precision is too large to format")?;
+    let int_part = magnitude / scale;
+    let mut frac_part = magnitude % scale;
+
+    let mut int_digits = [0u8; MAX_DECIMAL_STR_LEN];
+    let mut n = 0;
+    if int_part == 0 {
+        int_digits[0] = b'0';
+        n = 1;
+    } else {
+        let mut v = int_part;
+        while v > 0 {
+            int_digits[n] = b'0' + (v % 10) as u8;
+            v /= 10;
+            n += 1;
+        }
+    }
+    for i in (0..n).rev() {
+        out.push(int_digits[i])?;
+    }
+
+    if precision > 0 {
+        let mut frac_digits = [0u8; MAX_DECIMAL_STR_LEN];
+        for i in (0..precision as usize).rev() {
+            frac_digits[i] = b'0' + (frac_part % 10) as u8;
+            frac_part /= 10;
+        }
+        let mut end = precision as usize;
+        while end > 0 && frac_digits[end - 1] == b'0' {
+            end -= 1;
+        }
+        if end > 0 {
+            out.push(b'.')?;
+            for b in &frac_digits[..end] {
+                out.push(*b)?;
+            }
+        }
+    }
+    Ok(out)
 }
 
 #[derive(PartialEq, PartialOrd, Ord, Eq, Clone, Copy, Encode, Decode)]
@@ -162,6 +595,64 @@ impl AssetErr {
     }
 }
 
+/// Generic multi-asset accessor surface over this module's storage, mirroring how newer
+/// Substrate pallets expose `pallet-assets` through a `Fungibles`-style trait instead of
+/// requiring callers to reach into `TotalAssetBalance`/per-account storage directly. Downstream
+/// pallets (DEX, gateway, fee) should depend on this trait rather than `Module<T>`'s storage
+/// items, so a mock implementation can stand in for tests.
+pub trait Fungibles<AccountId> {
+    type Balance;
+    type PositiveImbalance: Imbalance<Self::Balance, Opposite = Self::NegativeImbalance>;
+    type NegativeImbalance: Imbalance<Self::Balance, Opposite = Self::PositiveImbalance>;
+
+    /// The free/reserved-for-`type_` balance `who` holds in `token`.
+    fn balance(token: &Token, who: &AccountId, type_: AssetType) -> Self::Balance;
+    /// The total issuance of `token` across all accounts and `AssetType`s.
+    fn total_issuance(token: &Token) -> Self::Balance;
+
+    /// Dry-run check for whether `value` can be minted into `who`'s `type_` balance in
+    /// `token` without actually moving anything.
+    fn can_deposit(
+        token: &Token,
+        who: &AccountId,
+        type_: AssetType,
+        value: Self::Balance,
+    ) -> result::Result<(), AssetErr>;
+    /// Dry-run check for whether `value` can be withdrawn from `who`'s `type_` balance in
+    /// `token` without actually moving anything.
+    fn can_withdraw(
+        token: &Token,
+        who: &AccountId,
+        type_: AssetType,
+        value: Self::Balance,
+    ) -> result::Result<(), AssetErr>;
+
+    /// Moves `value` of `token`'s `type_` balance from `from` to `to`.
+    fn transfer(
+        token: &Token,
+        from: &AccountId,
+        to: &AccountId,
+        type_: AssetType,
+        value: Self::Balance,
+    ) -> Result;
+    /// Mints `value` of `token` into `who`'s `type_` balance, returning the resulting
+    /// positive imbalance for the caller to route (e.g. via an `OnAssetImbalance` handler).
+    fn mint_into(
+        token: &Token,
+        who: &AccountId,
+        type_: AssetType,
+        value: Self::Balance,
+    ) -> result::Result<Self::PositiveImbalance, AssetErr>;
+    /// Burns `value` of `token` from `who`'s `type_` balance, returning the resulting
+    /// negative imbalance for the caller to route.
+    fn burn_from(
+        token: &Token,
+        who: &AccountId,
+        type_: AssetType,
+        value: Self::Balance,
+    ) -> result::Result<Self::NegativeImbalance, AssetErr>;
+}
+
 /// Token can only use numbers (0x30~0x39), capital letters (0x41~0x5A), lowercase letters (0x61~0x7A), -(0x2D), .(0x2E), |(0x7C),  ~(0x7E).
 pub fn is_valid_token(v: &[u8]) -> Result {
     if v.len() > MAX_TOKEN_LEN || v.is_empty() {
@@ -223,8 +714,12 @@ pub fn is_valid_memo<T: Trait>(msg: &Memo) -> Result {
 }
 
 mod imbalances {
-    use super::{result, AssetType, ChainT, Imbalance, Saturating, StorageMap, Token, Zero};
+    use super::{
+        result, AssetType, ChainT, Get, Imbalance, OnUnbalanced, Saturating, StorageMap, Token,
+        Zero,
+    };
     use crate::{Module, TotalAssetBalance, Trait};
+    use rstd::marker::PhantomData;
     use rstd::mem;
 
     /// Opaque, move-only struct with private fields that serves as a token denoting that
@@ -236,6 +731,15 @@ mod imbalances {
         pub fn new(amount: T::Balance, token: Token, type_: AssetType) -> Self {
             PositiveImbalance(amount, token, type_)
         }
+
+        /// Breaks the imbalance into its raw parts without running `Drop`, for an
+        /// `OnUnbalanced` handler that wants to route the funds itself instead of falling
+        /// back to `SquareUpTotalIssuance`.
+        pub fn deconstruct(self) -> (Token, AssetType, T::Balance) {
+            let parts = (self.1.clone(), self.2, self.0.clone());
+            mem::forget(self);
+            parts
+        }
     }
 
     /// Opaque, move-only struct with private fields that serves as a token denoting that
@@ -247,6 +751,15 @@ mod imbalances {
         pub fn new(amount: T::Balance, token: Token, type_: AssetType) -> Self {
             NegativeImbalance(amount, token, type_)
         }
+
+        /// Breaks the imbalance into its raw parts without running `Drop`, for an
+        /// `OnUnbalanced` handler that wants to route the funds itself instead of falling
+        /// back to `SquareUpTotalIssuance`.
+        pub fn deconstruct(self) -> (Token, AssetType, T::Balance) {
+            let parts = (self.1.clone(), self.2, self.0.clone());
+            mem::forget(self);
+            parts
+        }
     }
 
     impl<T: Trait> Imbalance<T::Balance> for PositiveImbalance<T> {
@@ -365,23 +878,450 @@ mod imbalances {
     }
 
     impl<T: Trait> Drop for PositiveImbalance<T> {
-        /// Basic drop handler will just square up the total issuance.
+        /// Hands the imbalance to the configured `T::OnAssetImbalance` handler instead of
+        /// unconditionally squaring up the total issuance, symmetric with `NegativeImbalance`'s
+        /// drop handler. `SquareUpTotalIssuance` reproduces the previous unconditional behaviour
+        /// and is the handler a runtime gets by default.
         fn drop(&mut self) {
-            TotalAssetBalance::<T>::mutate(&self.1, |map| {
-                let balance = map.entry(self.2).or_default();
-                *balance = balance.saturating_add(self.0)
-            })
+            let amount = mem::replace(&mut self.0, Zero::zero());
+            T::OnAssetImbalance::on_unbalanced(PositiveImbalance::new(
+                amount,
+                self.1.clone(),
+                self.2,
+            ));
         }
     }
 
     impl<T: Trait> Drop for NegativeImbalance<T> {
-        /// Basic drop handler will just square up the total issuance.
+        /// Hands the imbalance to the configured `T::OnAssetImbalance` handler instead of
+        /// unconditionally squaring up the total issuance, so slashed/fee-collected funds can
+        /// be redirected elsewhere. `SquareUpTotalIssuance` reproduces the previous behaviour
+        /// and is the handler a runtime gets by default.
         fn drop(&mut self) {
-            TotalAssetBalance::<T>::mutate(&self.1, |map| {
-                let balance = map.entry(self.2).or_default();
-                *balance = balance.saturating_sub(self.0)
+            let amount = mem::replace(&mut self.0, Zero::zero());
+            T::OnAssetImbalance::on_unbalanced(NegativeImbalance::new(
+                amount,
+                self.1.clone(),
+                self.2,
+            ));
+        }
+    }
+
+    /// Default `OnAssetImbalance` handler: squares up `TotalAssetBalance` and discards the
+    /// imbalance, exactly as the unconditional `Drop` impl used to.
+    pub struct SquareUpTotalIssuance<T>(PhantomData<T>);
+
+    impl<T: Trait> OnUnbalanced<NegativeImbalance<T>> for SquareUpTotalIssuance<T> {
+        fn on_unbalanced(amount: NegativeImbalance<T>) {
+            let (token, type_, value) = amount.deconstruct();
+            TotalAssetBalance::<T>::mutate(&token, |map| {
+                let balance = map.entry(type_).or_default();
+                *balance = balance.saturating_sub(value)
+            })
+        }
+    }
+
+    impl<T: Trait> OnUnbalanced<PositiveImbalance<T>> for SquareUpTotalIssuance<T> {
+        fn on_unbalanced(amount: PositiveImbalance<T>) {
+            let (token, type_, value) = amount.deconstruct();
+            TotalAssetBalance::<T>::mutate(&token, |map| {
+                let balance = map.entry(type_).or_default();
+                *balance = balance.saturating_add(value)
             })
         }
     }
 
+    /// `OnAssetImbalance` handler that credits a fixed account's `AssetType::Free` balance in
+    /// the same token instead of letting the funds fall back to `SquareUpTotalIssuance`;
+    /// useful for routing slashed or fee-collected funds to a treasury account. `Account`
+    /// supplies the beneficiary, following the same `Get`-parameterized pattern Substrate uses
+    /// for e.g. the treasury pallet's module account.
+    pub struct CreditAccount<T, Account>(PhantomData<(T, Account)>);
+
+    impl<T: Trait, Account: Get<T::AccountId>> OnUnbalanced<NegativeImbalance<T>>
+        for CreditAccount<T, Account>
+    {
+        fn on_unbalanced(amount: NegativeImbalance<T>) {
+            let (token, _, value) = amount.deconstruct();
+            crate::AssetBalance::<T>::mutate(&(Account::get(), token), |map| {
+                let balance = map.entry(AssetType::Free).or_default();
+                *balance = balance.saturating_add(value)
+            })
+        }
+    }
+
+    impl<T: Trait, Account: Get<T::AccountId>> OnUnbalanced<PositiveImbalance<T>>
+        for CreditAccount<T, Account>
+    {
+        fn on_unbalanced(amount: PositiveImbalance<T>) {
+            let (token, _, value) = amount.deconstruct();
+            crate::AssetBalance::<T>::mutate(&(Account::get(), token), |map| {
+                let balance = map.entry(AssetType::Free).or_default();
+                *balance = balance.saturating_add(value)
+            })
+        }
+    }
+}
+
+impl<T: Trait> Fungibles<T::AccountId> for Module<T> {
+    type Balance = T::Balance;
+    type PositiveImbalance = PositiveImbalance<T>;
+    type NegativeImbalance = NegativeImbalance<T>;
+
+    fn balance(token: &Token, who: &T::AccountId, type_: AssetType) -> T::Balance {
+        crate::AssetBalance::<T>::get(&(who.clone(), token.clone()))
+            .get(&type_)
+            .cloned()
+            .unwrap_or_else(Zero::zero)
+    }
+
+    fn total_issuance(token: &Token) -> T::Balance {
+        let totals = TotalAssetBalance::<T>::get(token);
+        AssetType::iterator().fold(Zero::zero(), |acc: T::Balance, type_| {
+            acc.saturating_add(totals.get(type_).cloned().unwrap_or_else(Zero::zero))
+        })
+    }
+
+    fn can_deposit(
+        token: &Token,
+        _who: &T::AccountId,
+        _type_: AssetType,
+        value: T::Balance,
+    ) -> result::Result<(), AssetErr> {
+        let current_total = Self::total_issuance(token);
+        current_total
+            .checked_add(&value)
+            .ok_or(AssetErr::TotalAssetOverFlow)?;
+        // Also enforce the asset's own `max_money` cap, not just `T::Balance`'s overflow
+        // boundary, so a registered asset can never be minted past the total supply it was
+        // created with.
+        if let Some(asset) = crate::AssetInfo::<T>::get(token) {
+            asset
+                .checked_total_after_mint::<T>(current_total, value)
+                .map_err(|_| AssetErr::TotalAssetOverFlow)?;
+        }
+        Ok(())
+    }
+
+    fn can_withdraw(
+        token: &Token,
+        who: &T::AccountId,
+        type_: AssetType,
+        value: T::Balance,
+    ) -> result::Result<(), AssetErr> {
+        if Self::balance(token, who, type_) < value {
+            return Err(AssetErr::NotEnough);
+        }
+        Ok(())
+    }
+
+    fn transfer(
+        token: &Token,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        type_: AssetType,
+        value: T::Balance,
+    ) -> Result {
+        // Validate both sides before mutating either: if `to`'s deposit were checked only
+        // after `from` was already debited, a failing `can_deposit` would leave the burned
+        // `NegativeImbalance` to fall back to `T::OnAssetImbalance`/`SquareUpTotalIssuance` on
+        // drop, silently destroying the funds instead of failing the transfer cleanly.
+        Self::can_withdraw(token, from, type_, value).map_err(AssetErr::info)?;
+        Self::can_deposit(token, to, type_, value).map_err(AssetErr::info)?;
+        let imbalance = Self::burn_from(token, from, type_, value).map_err(AssetErr::info)?;
+        let credit = Self::mint_into(token, to, type_, value).map_err(AssetErr::info)?;
+        imbalance
+            .offset(credit)
+            .map(|_| ())
+            .map_err(|_| "transfer imbalance did not net to zero")
+    }
+
+    fn mint_into(
+        token: &Token,
+        who: &T::AccountId,
+        type_: AssetType,
+        value: T::Balance,
+    ) -> result::Result<PositiveImbalance<T>, AssetErr> {
+        Self::can_deposit(token, who, type_, value)?;
+        crate::AssetBalance::<T>::mutate(&(who.clone(), token.clone()), |map| {
+            let balance = map.entry(type_).or_default();
+            *balance = balance.saturating_add(value)
+        });
+        Ok(PositiveImbalance::new(value, token.clone(), type_))
+    }
+
+    fn burn_from(
+        token: &Token,
+        who: &T::AccountId,
+        type_: AssetType,
+        value: T::Balance,
+    ) -> result::Result<NegativeImbalance<T>, AssetErr> {
+        Self::can_withdraw(token, who, type_, value)?;
+        crate::AssetBalance::<T>::mutate(&(who.clone(), token.clone()), |map| {
+            let balance = map.entry(type_).or_default();
+            *balance = balance.saturating_sub(value)
+        });
+        Ok(NegativeImbalance::new(value, token.clone(), type_))
+    }
+}
+
+/// Converts a fee quoted in the native token's minimal units into an arbitrary token's
+/// minimal units, keyed on that token's own `precision`, so `TokenCurrency` can charge fees
+/// in any whitelisted asset instead of assuming everyone prices fees the same way. Fails
+/// rather than clamping, so an asset precision too large to convert cleanly rejects the fee
+/// payment instead of silently charging nothing.
+pub trait FeeConverter<Balance> {
+    fn convert(native_fee: Balance, precision: Precision) -> result::Result<Balance, &'static str>;
+}
+
+/// Default `FeeConverter`: rescales a native fee (quoted at `COIN`'s 8 decimals) to the
+/// target token's own `precision`, e.g. a fee of `1 * COIN` becomes `1 * 10^precision` in the
+/// fee token.
+pub struct ScaleByPrecision;
+
+impl<Balance: primitives::traits::As<u64>> FeeConverter<Balance> for ScaleByPrecision {
+    fn convert(native_fee: Balance, precision: Precision) -> result::Result<Balance, &'static str> {
+        let native_fee = i128::from(native_fee.as_());
+        let scale = 10i128
+            .checked_pow(precision as u32)
+            .ok_or("fee token precision is too large to convert the fee")?;
+        let converted = native_fee
+            .checked_mul(scale)
+            .and_then(|v| v.checked_div(COIN))
+            .ok_or("fee conversion overflowed")?;
+        let converted = u64::try_from(converted).map_err(|_| "fee conversion overflowed")?;
+        Ok(Balance::sa(converted))
+    }
+}
+
+/// Adapts this module's `(Token, AssetType::Free)` balances for the fixed token `GetToken`
+/// supplies to `support::traits::Currency`, so the fee manager can withdraw and refund
+/// transaction fees in any token whitelisted via `Asset::accept_fee`. `Converter` maps the
+/// native-unit fee `T::TransactionPayment` computes into this token's own minimal units
+/// before it ever reaches storage.
+pub struct TokenCurrency<T, GetToken, Converter>(PhantomData<(T, GetToken, Converter)>);
+
+impl<T: Trait, GetToken: Get<Token>, Converter: FeeConverter<T::Balance>>
+    TokenCurrency<T, GetToken, Converter>
+{
+    /// Looks up `GetToken`'s registered `Asset`, rejecting tokens that either don't exist or
+    /// were never whitelisted via `Asset::set_accept_fee`.
+    fn fee_asset() -> result::Result<Asset, &'static str> {
+        let asset =
+            crate::AssetInfo::<T>::get(GetToken::get()).ok_or("fee token is not a registered asset")?;
+        if !asset.accept_fee() {
+            return Err("this asset is not whitelisted for transaction fees");
+        }
+        Ok(asset)
+    }
+}
+
+impl<T: Trait, GetToken: Get<Token>, Converter: FeeConverter<T::Balance>> Currency<T::AccountId>
+    for TokenCurrency<T, GetToken, Converter>
+{
+    type Balance = T::Balance;
+    type PositiveImbalance = PositiveImbalance<T>;
+    type NegativeImbalance = NegativeImbalance<T>;
+
+    fn total_balance(who: &T::AccountId) -> T::Balance {
+        Module::<T>::balance(&GetToken::get(), who, AssetType::Free)
+    }
+
+    fn can_slash(who: &T::AccountId, value: T::Balance) -> bool {
+        Self::total_balance(who) >= value
+    }
+
+    fn total_issuance() -> T::Balance {
+        Module::<T>::total_issuance(&GetToken::get())
+    }
+
+    fn minimum_balance() -> T::Balance {
+        Zero::zero()
+    }
+
+    /// Immediately removes `amount` from `GetToken`'s total issuance and returns a
+    /// `PositiveImbalance` that reverses the removal if it's dropped unmatched, so a
+    /// bare `burn` followed by a balance decrease nets to a real reduction in supply, while an
+    /// unmatched `burn` nets to zero instead of permanently inflating `TotalAssetBalance`.
+    fn burn(amount: T::Balance) -> Self::PositiveImbalance {
+        crate::TotalAssetBalance::<T>::mutate(&GetToken::get(), |map| {
+            let balance = map.entry(AssetType::Free).or_default();
+            *balance = balance.saturating_sub(amount)
+        });
+        PositiveImbalance::new(amount, GetToken::get(), AssetType::Free)
+    }
+
+    /// Immediately adds `amount` to `GetToken`'s total issuance and returns a
+    /// `NegativeImbalance` that reverses the addition if it's dropped unmatched, symmetric
+    /// with `burn`.
+    fn issue(amount: T::Balance) -> Self::NegativeImbalance {
+        crate::TotalAssetBalance::<T>::mutate(&GetToken::get(), |map| {
+            let balance = map.entry(AssetType::Free).or_default();
+            *balance = balance.saturating_add(amount)
+        });
+        NegativeImbalance::new(amount, GetToken::get(), AssetType::Free)
+    }
+
+    fn free_balance(who: &T::AccountId) -> T::Balance {
+        Self::total_balance(who)
+    }
+
+    fn ensure_can_withdraw(
+        who: &T::AccountId,
+        amount: T::Balance,
+        _reasons: WithdrawReasons,
+        _new_balance: T::Balance,
+    ) -> Result {
+        Module::<T>::can_withdraw(&GetToken::get(), who, AssetType::Free, amount)
+            .map_err(AssetErr::info)
+    }
+
+    fn transfer(transactor: &T::AccountId, dest: &T::AccountId, value: T::Balance) -> Result {
+        Module::<T>::transfer(&GetToken::get(), transactor, dest, AssetType::Free, value)
+    }
+
+    /// Withdraws `value` (quoted in the native fee unit and rescaled via `Converter`) to pay a
+    /// transaction fee, returning the negative imbalance the fee manager should route through
+    /// `T::OnAssetImbalance` once the fee is finalised. Fails unless `GetToken` is a registered
+    /// asset whitelisted via `Asset::set_accept_fee`.
+    fn withdraw(
+        who: &T::AccountId,
+        value: T::Balance,
+        _reasons: WithdrawReasons,
+        _liveness: ExistenceRequirement,
+    ) -> result::Result<Self::NegativeImbalance, &'static str> {
+        let asset = Self::fee_asset()?;
+        let value = Converter::convert(value, asset.precision())?;
+        Module::<T>::burn_from(&GetToken::get(), who, AssetType::Free, value)
+            .map_err(AssetErr::info)
+    }
+
+    fn slash(who: &T::AccountId, value: T::Balance) -> (Self::NegativeImbalance, T::Balance) {
+        let available = Self::total_balance(who);
+        let to_slash = available.min(value);
+        let imbalance = Module::<T>::burn_from(&GetToken::get(), who, AssetType::Free, to_slash)
+            .unwrap_or_else(|_| NegativeImbalance::zero());
+        (imbalance, value - to_slash)
+    }
+
+    /// Credits a fee refund (e.g. for an over-estimated weight), rescaled via `Converter` from
+    /// the native fee unit back into `who`'s balance.
+    fn deposit_into_existing(
+        who: &T::AccountId,
+        value: T::Balance,
+    ) -> result::Result<Self::PositiveImbalance, &'static str> {
+        let asset = Self::fee_asset()?;
+        let value = Converter::convert(value, asset.precision())?;
+        Module::<T>::mint_into(&GetToken::get(), who, AssetType::Free, value)
+            .map_err(AssetErr::info)
+    }
+
+    fn deposit_creating(who: &T::AccountId, value: T::Balance) -> Self::PositiveImbalance {
+        Module::<T>::mint_into(&GetToken::get(), who, AssetType::Free, value)
+            .unwrap_or_else(|_| PositiveImbalance::zero())
+    }
+
+    fn make_free_balance_be(
+        who: &T::AccountId,
+        balance: T::Balance,
+    ) -> SignedImbalance<T::Balance, Self::PositiveImbalance> {
+        let current = Self::total_balance(who);
+        if balance >= current {
+            SignedImbalance::Positive(Self::deposit_creating(who, balance - current))
+        } else {
+            SignedImbalance::Negative(
+                Module::<T>::burn_from(&GetToken::get(), who, AssetType::Free, current - balance)
+                    .unwrap_or_else(|_| NegativeImbalance::zero()),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amount_rejects_out_of_range_values() {
+        assert!(Amount::from_i128(MAX_MONEY).is_ok());
+        assert!(Amount::from_i128(-MAX_MONEY).is_ok());
+        assert!(Amount::from_i128(MAX_MONEY + 1).is_err());
+        assert!(Amount::from_i128(-MAX_MONEY - 1).is_err());
+    }
+
+    #[test]
+    fn amount_add_and_sub_are_checked() {
+        let a = Amount::from_i128(10).unwrap();
+        let b = Amount::from_i128(3).unwrap();
+        assert_eq!((a + b).unwrap().value(), 13);
+        assert_eq!((a - b).unwrap().value(), 7);
+
+        let max = Amount::from_i128(MAX_MONEY).unwrap();
+        let one = Amount::from_i128(1).unwrap();
+        assert!((max + one).is_none());
+    }
+
+    #[test]
+    fn amount_sum_folds_checked() {
+        let values = vec![
+            Amount::from_i128(1).unwrap(),
+            Amount::from_i128(2).unwrap(),
+            Amount::from_i128(3).unwrap(),
+        ];
+        let total: Option<Amount> = values.into_iter().sum();
+        assert_eq!(total.unwrap().value(), 6);
+    }
+
+    #[test]
+    fn decimal_str_round_trips_positive_and_negative() {
+        for input in &["1.2345", "0.1", "42", "-1.2345", "-42"] {
+            let parsed = parse_decimal_str(input, 4, MAX_MONEY).unwrap();
+            let formatted = format_decimal_str(parsed, 4).unwrap();
+            assert_eq!(&*formatted, *input);
+        }
+    }
+
+    #[test]
+    fn decimal_str_rejects_bad_input() {
+        assert_eq!(
+            parse_decimal_str("1.2.3", 4, MAX_MONEY),
+            Err(DecimalStrErr::MultipleDecimalPoints)
+        );
+        assert_eq!(
+            parse_decimal_str("1.23456", 4, MAX_MONEY),
+            Err(DecimalStrErr::TooManyFractionalDigits)
+        );
+        assert_eq!(parse_decimal_str("1a", 4, MAX_MONEY), Err(DecimalStrErr::InvalidChar));
+    }
+
+    #[test]
+    fn chain_iterator_yields_every_reserved_variant_in_order() {
+        let ids: Vec<ChainId> = Chain::iterator().map(|c| c.id()).collect();
+        assert_eq!(ids, vec![CHAINX_CHAIN_ID, BITCOIN_CHAIN_ID, ETHEREUM_CHAIN_ID]);
+        assert_eq!(Chain::iterator().count(), RESERVED_CHAIN_COUNT as usize);
+    }
+
+    #[test]
+    fn scale_by_precision_converts_native_fee_to_token_precision() {
+        // 1 * COIN (8 decimals) at precision 4 should become 1 * 10^4.
+        let converted = ScaleByPrecision::convert(COIN as u64, 4).unwrap();
+        assert_eq!(converted, 10_000u64);
+    }
+
+    #[test]
+    fn scale_by_precision_rejects_an_unconvertible_precision() {
+        assert!(ScaleByPrecision::convert(COIN as u64, 255).is_err());
+    }
+
+    // `Fungibles::transfer`/`mint_into`/`burn_from` cap enforcement (chunk0-4),
+    // `PositiveImbalance`/`NegativeImbalance` `Drop` routing through `OnAssetImbalance`
+    // (chunk0-2), and `TokenCurrency::burn`/`issue` against a mock `OnAssetImbalance` handler
+    // (chunk0-6) all exercise storage this crate's `decl_storage!`/`decl_module!` macros
+    // generate in `lib.rs` (`AssetBalance`, `TotalAssetBalance`, `AssetInfo`, `ChainRegistry`)
+    // plus a full mock runtime (`impl Trait for Test`, `with_externalities`). None of that
+    // exists in this tree — only `types.rs` is present, with no `lib.rs`/`Cargo.toml` anywhere
+    // in the repo — so those paths can't be exercised from here; the pure helpers above
+    // (`ScaleByPrecision`, `Chain`, `Amount`, decimal parse/format) are covered instead. Once
+    // `lib.rs`'s `mock.rs` exists, storage-backed tests for
+    // `can_deposit`/`transfer`/`burn`/`issue`/the imbalance `Drop` impls belong there.
 }